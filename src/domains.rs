@@ -0,0 +1,41 @@
+use anyhow::{Result, anyhow};
+
+/// Metadata for an EVM chain this service can produce proofs for.
+#[derive(Debug, Clone, Copy)]
+pub struct ChainMetadata {
+    pub domain: &'static str,
+    pub chain_id: u64,
+}
+
+/// The set of domains this service supports, keyed by domain name.
+///
+/// This is the single source of truth mapping a `domain` string in a
+/// request to the chain it refers to; add an entry here to support a new
+/// chain. `resolve` is the only way callers look up a domain.
+const REGISTRY: &[ChainMetadata] = &[
+    ChainMetadata {
+        domain: "ethereum",
+        chain_id: 1,
+    },
+    ChainMetadata {
+        domain: "base",
+        chain_id: 8453,
+    },
+    ChainMetadata {
+        domain: "arbitrum",
+        chain_id: 42161,
+    },
+];
+
+/// Looks up chain metadata for a domain name, e.g. `"ethereum"`.
+///
+/// # Errors
+///
+/// Returns an error if `domain` is not in the registry.
+pub fn resolve(domain: &str) -> Result<ChainMetadata> {
+    REGISTRY
+        .iter()
+        .copied()
+        .find(|chain| chain.domain == domain)
+        .ok_or_else(|| anyhow!("unsupported domain: {}", domain))
+}
@@ -0,0 +1,88 @@
+use axum::Json;
+use axum::http::StatusCode;
+use axum::response::{IntoResponse, Response};
+use serde_json::json;
+use std::fmt;
+
+/// Errors that can occur while producing a state proof.
+///
+/// This type distinguishes failures while fetching or decoding data (surfaced
+/// as `500`) from proofs that were fetched but failed local verification
+/// against the block's state root (surfaced as `422`, since the request was
+/// well-formed but the data it produced can't be trusted), and from requests
+/// with an unsupported domain, a mismatched chain, or no RPC URL (surfaced as
+/// `400`).
+#[derive(Debug)]
+pub enum ProofError {
+    /// The upstream Ethereum RPC request failed, or returned malformed data.
+    Rpc(anyhow::Error),
+    /// The proof was retrieved but does not verify against the fetched state root.
+    VerificationFailed(String),
+    /// The requested `domain` is unsupported, or the RPC endpoint's `eth_chainId`
+    /// doesn't match the chain the domain refers to.
+    DomainMismatch(String),
+    /// The request body is well-formed JSON but doesn't carry enough
+    /// information to resolve a proof, e.g. neither `ethereum_url` nor
+    /// `ethereum_urls` was supplied.
+    InvalidRequest(String),
+}
+
+impl fmt::Display for ProofError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProofError::Rpc(e) => write!(f, "{}", e),
+            ProofError::VerificationFailed(msg) => write!(f, "{}", msg),
+            ProofError::DomainMismatch(msg) => write!(f, "{}", msg),
+            ProofError::InvalidRequest(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for ProofError {}
+
+impl From<anyhow::Error> for ProofError {
+    fn from(e: anyhow::Error) -> Self {
+        ProofError::Rpc(e)
+    }
+}
+
+impl From<serde_json::Error> for ProofError {
+    fn from(e: serde_json::Error) -> Self {
+        ProofError::Rpc(e.into())
+    }
+}
+
+impl IntoResponse for ProofError {
+    fn into_response(self) -> Response {
+        match self {
+            ProofError::Rpc(e) => {
+                let error_response = json!({
+                    "status": 500,
+                    "error": format!("Error getting state proof: {}", e),
+                });
+                (StatusCode::INTERNAL_SERVER_ERROR, Json(error_response)).into_response()
+            }
+            ProofError::VerificationFailed(msg) => {
+                let error_response = json!({
+                    "status": 422,
+                    "error": format!("Proof verification failed: {}", msg),
+                });
+                (StatusCode::UNPROCESSABLE_ENTITY, Json(error_response)).into_response()
+            }
+            ProofError::DomainMismatch(msg) => {
+                let error_response = json!({
+                    "status": 400,
+                    "error": msg,
+                });
+                (StatusCode::BAD_REQUEST, Json(error_response)).into_response()
+            }
+            ProofError::InvalidRequest(msg) => {
+                let error_response = json!({
+                    "status": 400,
+                    "error": msg,
+                });
+                (StatusCode::BAD_REQUEST, Json(error_response)).into_response()
+            }
+        }
+    }
+}
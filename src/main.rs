@@ -1,39 +1,88 @@
-use crate::util::get_state_proof;
+use crate::rpc::BlockSelector;
+use crate::util::{get_state_proof, get_state_proof_quorum};
 use axum::{
     Router,
     extract::Json,
     extract::rejection::JsonRejection,
     http::StatusCode,
     response::{IntoResponse, Response},
-    routing::post,
+    routing::{get, post},
 };
 use serde::Deserialize;
 use serde_json::json;
 use tower_http::cors::{Any, CorsLayer};
 
+mod domains;
+mod error;
+mod merkle;
+mod rpc;
 mod util;
+mod ws;
+
+use crate::ws::SubscriptionManager;
 
 /// Request structure for the state proof endpoint.
 ///
 /// This struct represents the JSON payload expected by the state proof endpoint.
-/// All fields are required except for `key`, which is optional.
+/// All fields are required except for `ethereum_url`, `ethereum_urls`, `key`,
+/// `min_agreement`, and `domain` — though at least one of `ethereum_url`/`ethereum_urls`
+/// must be present, which [`resolve_state_proof`] checks since serde can't
+/// express an either-or requirement between two optional fields.
 ///
 /// # Fields
 ///
 /// * `address` - The Ethereum address to get the proof for (hex string, 0x-prefixed)
-/// * `ethereum_url` - The RPC URL for the Ethereum node (e.g., Infura, Alchemy)
-/// * `height` - The block height/number to get the proof for
+/// * `ethereum_url` - The RPC URL for the Ethereum node (e.g., Infura, Alchemy).
+///   Required unless `ethereum_urls` is set.
+/// * `ethereum_urls` - Optional list of RPC URLs to query for quorum agreement. When
+///   present, this takes precedence over `ethereum_url` and the request succeeds only
+///   once at least `min_agreement` endpoints return a byte-identical proof.
+/// * `min_agreement` - Minimum number of `ethereum_urls` that must agree. Defaults to
+///   all of them when `ethereum_urls` is set and this is omitted.
+/// * `domain` - The chain this proof is sourced from, e.g. `"ethereum"`, `"base"`,
+///   `"arbitrum"`. Defaults to `"ethereum"`. Must match `ethereum_url`'s `eth_chainId`.
+/// * `height` - The block to get the proof for: a concrete number, a symbolic tag
+///   (`"latest"`, `"safe"`, `"finalized"`, `"earliest"`, `"pending"`), or a
+///   32-byte block hash as a `0x`-prefixed hex string
 /// * `key` - Optional storage slot key for storage proofs (hex string, 0x-prefixed)
 #[derive(Debug, Deserialize)]
 struct StateProofRequest {
     address: String,
-    ethereum_url: String,
-    height: u64,
+    #[serde(default)]
+    ethereum_url: Option<String>,
+    #[serde(default)]
+    ethereum_urls: Option<Vec<String>>,
+    #[serde(default)]
+    min_agreement: Option<usize>,
+    #[serde(default = "default_domain")]
+    domain: String,
+    height: BlockSelector,
     #[serde(default)]
     #[serde(deserialize_with = "deserialize_empty_string_as_none")]
     key: Option<String>,
 }
 
+/// The domain assumed when a request doesn't specify one, for compatibility
+/// with callers predating multi-chain support.
+fn default_domain() -> String {
+    "ethereum".to_string()
+}
+
+/// The body accepted by the state proof endpoint.
+///
+/// Accepts either a single request object, a bare JSON array of requests, or
+/// an object with a `requests` field, so a caller fetching many account/storage
+/// proofs for the same block can do so in one round trip. Variants are tried
+/// in declaration order: the `requests`-wrapped form first, then a bare
+/// array, then a single object.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum StateProofRequestBody {
+    Batch { requests: Vec<StateProofRequest> },
+    Array(Vec<StateProofRequest>),
+    Single(StateProofRequest),
+}
+
 /// Custom deserializer to treat empty strings as None.
 ///
 /// This function is used to deserialize optional string fields in the request.
@@ -69,8 +118,12 @@ async fn main() {
         .allow_methods(Any)
         .allow_headers(Any);
 
+    let subscription_manager = SubscriptionManager::new();
+
     let app = Router::new()
         .route("/", post(handle_state_proof))
+        .route("/ws", get(ws::watch_handler))
+        .with_state(subscription_manager)
         .layer(cors);
 
     let listener = tokio::net::TcpListener::bind("0.0.0.0:7777").await.unwrap();
@@ -97,9 +150,19 @@ async fn main() {
 /// Returns an Axum response containing either:
 /// * The state proof for valid requests
 /// * An error message for invalid requests
-async fn handle_state_proof(result: Result<Json<StateProofRequest>, JsonRejection>) -> Response {
+async fn handle_state_proof(
+    result: Result<Json<StateProofRequestBody>, JsonRejection>,
+) -> Response {
     match result {
-        Ok(payload) => {
+        Ok(Json(StateProofRequestBody::Batch { requests })) => {
+            println!("Request Ok! (batch of {})", requests.len());
+            handle_batch(requests).await.into_response()
+        }
+        Ok(Json(StateProofRequestBody::Array(requests))) => {
+            println!("Request Ok! (batch of {})", requests.len());
+            handle_batch(requests).await.into_response()
+        }
+        Ok(Json(StateProofRequestBody::Single(payload))) => {
             println!("Request Ok!");
             get_state_proof_handler(payload).await.into_response()
         }
@@ -114,6 +177,26 @@ async fn handle_state_proof(result: Result<Json<StateProofRequest>, JsonRejectio
     }
 }
 
+/// Resolves a batch of requests concurrently, preserving the caller's order.
+///
+/// Each item succeeds or fails independently: a failure is reported as a
+/// `{"error": ...}` object in that item's slot rather than failing the whole
+/// batch, mirroring how a bridge or coprocessor building a multi-account
+/// snapshot wants partial results over an all-or-nothing round trip.
+async fn handle_batch(requests: Vec<StateProofRequest>) -> impl IntoResponse {
+    let results = futures::future::join_all(requests.into_iter().map(|payload| async move {
+        match resolve_state_proof(&payload).await {
+            Ok(bytes) => {
+                serde_json::from_slice::<serde_json::Value>(&bytes).unwrap_or(serde_json::Value::Null)
+            }
+            Err(e) => json!({"error": e.to_string()}),
+        }
+    }))
+    .await;
+
+    Json(results)
+}
+
 /// Handler for the state proof endpoint.
 ///
 /// This function:
@@ -133,27 +216,53 @@ async fn handle_state_proof(result: Result<Json<StateProofRequest>, JsonRejectio
 use axum::body::Body;
 use axum::http::Response as HttpResponse;
 
-async fn get_state_proof_handler(Json(payload): Json<StateProofRequest>) -> impl IntoResponse {
-    match get_state_proof(
-        &payload.address,
-        &payload.ethereum_url,
-        payload.height,
-        payload.key.as_deref(),
-    )
-    .await
-    {
+async fn get_state_proof_handler(payload: StateProofRequest) -> impl IntoResponse {
+    match resolve_state_proof(&payload).await {
         Ok(json_bytes) => HttpResponse::builder()
             .status(StatusCode::OK)
             .body(Body::from(json_bytes))
             .unwrap()
             .into_response(),
 
-        Err(e) => {
-            let error_response = json!({
-                "status": 500,
-                "error": format!("Error getting state proof: {}", e)
-            });
-            (StatusCode::INTERNAL_SERVER_ERROR, Json(error_response)).into_response()
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Resolves a single request to its serialized `StateProof` bytes, routing to
+/// quorum mode when the caller supplied `ethereum_urls`.
+///
+/// # Errors
+///
+/// Returns `ProofError::InvalidRequest` if neither `ethereum_url` nor
+/// `ethereum_urls` was supplied.
+async fn resolve_state_proof(payload: &StateProofRequest) -> Result<Vec<u8>, crate::error::ProofError> {
+    match &payload.ethereum_urls {
+        Some(urls) => {
+            let min_agreement = payload.min_agreement.unwrap_or(urls.len());
+            get_state_proof_quorum(
+                &payload.address,
+                urls,
+                &payload.domain,
+                min_agreement,
+                &payload.height,
+                payload.key.as_deref(),
+            )
+            .await
+        }
+        None => {
+            let ethereum_url = payload.ethereum_url.as_deref().ok_or_else(|| {
+                crate::error::ProofError::InvalidRequest(
+                    "request must supply either `ethereum_url` or `ethereum_urls`".to_string(),
+                )
+            })?;
+            get_state_proof(
+                &payload.address,
+                ethereum_url,
+                &payload.domain,
+                &payload.height,
+                payload.key.as_deref(),
+            )
+            .await
         }
     }
 }
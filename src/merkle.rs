@@ -0,0 +1,417 @@
+use anyhow::{Result, anyhow};
+use rlp::{Rlp, RlpStream};
+use sha3::{Digest, Keccak256};
+
+/// `keccak256` of the empty byte string directly (code is never RLP-encoded
+/// before hashing) — the `codeHash` of an account with no code.
+pub const EMPTY_CODE_HASH: [u8; 32] = [
+    0xc5, 0xd2, 0x46, 0x01, 0x86, 0xf7, 0x23, 0x3c, 0x92, 0x7e, 0x7d, 0xb2, 0xdc, 0xc7, 0x03, 0xc0,
+    0xe5, 0x00, 0xb6, 0x53, 0xca, 0x82, 0x27, 0x3b, 0x7b, 0xfa, 0xd8, 0x04, 0x5d, 0x85, 0xa4, 0x70,
+];
+
+/// `keccak256` of the RLP encoding of an empty trie — the `storageRoot` of an
+/// account with no storage.
+pub const EMPTY_TRIE_ROOT: [u8; 32] = [
+    0x56, 0xe8, 0x1f, 0x17, 0x1b, 0xcc, 0x55, 0xa6, 0xff, 0x83, 0x45, 0xe6, 0x92, 0xc0, 0xf8, 0x6e,
+    0x5b, 0x48, 0xe0, 0x1b, 0x99, 0x6c, 0xad, 0xc0, 0x01, 0x62, 0x2f, 0xb5, 0xe3, 0x63, 0xb4, 0x21,
+];
+
+/// Computes the `keccak256` hash of `data`.
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak256::new();
+    hasher.update(data);
+    hasher.finalize().into()
+}
+
+/// Decodes a `0x`-prefixed hex string into bytes.
+pub fn decode_hex(s: &str) -> Result<Vec<u8>> {
+    let s = s.strip_prefix("0x").unwrap_or(s);
+    let s = if s.len() % 2 == 1 {
+        format!("0{}", s)
+    } else {
+        s.to_string()
+    };
+    Ok(hex::decode(s)?)
+}
+
+/// Decodes a `0x`-prefixed hex string into a fixed 32-byte array, left-padding with zeros.
+pub fn decode_hex32(s: &str) -> Result<[u8; 32]> {
+    let bytes = decode_hex(s)?;
+    if bytes.len() > 32 {
+        return Err(anyhow!("hex value longer than 32 bytes: {}", s));
+    }
+    let mut out = [0u8; 32];
+    out[32 - bytes.len()..].copy_from_slice(&bytes);
+    Ok(out)
+}
+
+/// Converts a byte string into its sequence of nibbles (half-bytes), high nibble first.
+fn to_nibbles(bytes: &[u8]) -> Vec<u8> {
+    let mut nibbles = Vec::with_capacity(bytes.len() * 2);
+    for byte in bytes {
+        nibbles.push(byte >> 4);
+        nibbles.push(byte & 0x0f);
+    }
+    nibbles
+}
+
+/// Strips a hex-prefix nibble (and, for extension/leaf nodes, the terminator flag)
+/// from an MPT path segment, returning the raw nibbles it encodes.
+fn strip_hex_prefix(nibbles: &[u8]) -> Vec<u8> {
+    if nibbles.is_empty() {
+        return Vec::new();
+    }
+    let odd = nibbles[0] & 0x1 == 1;
+    if odd {
+        nibbles[1..].to_vec()
+    } else {
+        nibbles[2..].to_vec()
+    }
+}
+
+/// How a trie node refers to one of its children.
+///
+/// Per the MPT spec, a child whose own RLP encoding is 32 bytes or longer is
+/// referenced by its `keccak256` hash (looked up as the next proof node); a
+/// child whose encoding is shorter is inlined directly in the parent instead
+/// of being hashed. Small tries — e.g. a contract with only a handful of
+/// populated storage slots — are full of inline children, so both forms have
+/// to be handled while walking a proof.
+enum NodeRef {
+    Hash([u8; 32]),
+    Inline(Vec<u8>),
+}
+
+/// Decodes a branch/extension child slot into the reference it holds, or
+/// `None` if the slot is empty (no child).
+fn decode_ref(item: &Rlp) -> Result<Option<NodeRef>> {
+    if item.is_list() {
+        return Ok(Some(NodeRef::Inline(item.as_raw().to_vec())));
+    }
+    let data = item.data()?;
+    if data.is_empty() {
+        return Ok(None);
+    }
+    if data.len() != 32 {
+        return Err(anyhow!("unexpected node reference length: {}", data.len()));
+    }
+    let mut hash = [0u8; 32];
+    hash.copy_from_slice(data);
+    Ok(Some(NodeRef::Hash(hash)))
+}
+
+/// Walks a Merkle-Patricia-Trie proof from `root` along `nibble_path`.
+///
+/// `proof_nodes` is the ordered list of RLP-encoded trie nodes returned by
+/// `eth_getProof` that are referenced by hash, starting at the root (always
+/// hash-referenced) and ending at the leaf or the point of divergence;
+/// inlined nodes encountered along the way are decoded directly from their
+/// parent instead of being looked up here.
+///
+/// `expected_value` is `Some(value)` to verify an inclusion proof (the walk
+/// must terminate in a leaf, or a branch's value slot, holding exactly
+/// `value`), or `None` to verify an exclusion proof (the walk must terminate
+/// in an empty child slot or a diverging extension/leaf path, proving the key
+/// is absent from the trie) — this covers a queried storage slot that was
+/// never set, or an account that was never touched.
+pub fn verify_branch(
+    root: [u8; 32],
+    nibble_path: &[u8],
+    proof_nodes: &[Vec<u8>],
+    expected_value: Option<&[u8]>,
+) -> Result<bool> {
+    let mut proof_nodes = proof_nodes.iter();
+    let mut path = nibble_path;
+    let mut pending_ref = NodeRef::Hash(root);
+
+    loop {
+        let node_bytes = match pending_ref {
+            NodeRef::Hash(hash) => {
+                let Some(node) = proof_nodes.next() else {
+                    return Ok(false);
+                };
+                if keccak256(node) != hash {
+                    return Ok(false);
+                }
+                node.clone()
+            }
+            NodeRef::Inline(bytes) => bytes,
+        };
+
+        let rlp = Rlp::new(&node_bytes);
+        let item_count = rlp.item_count()?;
+
+        if item_count == 17 {
+            if path.is_empty() {
+                let value: Vec<u8> = rlp.at(16)?.data()?.to_vec();
+                return Ok(match expected_value {
+                    Some(expected) => value == expected,
+                    None => value.is_empty(),
+                });
+            }
+
+            let nibble = path[0] as usize;
+            match decode_ref(&rlp.at(nibble)?)? {
+                None => return Ok(expected_value.is_none()),
+                Some(next_ref) => {
+                    pending_ref = next_ref;
+                    path = &path[1..];
+                }
+            }
+        } else if item_count == 2 {
+            let encoded_path: Vec<u8> = rlp.at(0)?.data()?.to_vec();
+            let path_nibbles = strip_hex_prefix(&to_nibbles(&encoded_path));
+            let is_leaf = encoded_path.first().map(|b| b >> 4 >= 2).unwrap_or(false);
+
+            let diverges =
+                path.len() < path_nibbles.len() || path[..path_nibbles.len()] != path_nibbles[..];
+            if diverges {
+                return Ok(expected_value.is_none());
+            }
+            path = &path[path_nibbles.len()..];
+
+            if is_leaf {
+                let value: Vec<u8> = rlp.at(1)?.data()?.to_vec();
+                return Ok(match expected_value {
+                    Some(expected) => path.is_empty() && value == expected,
+                    None => false,
+                });
+            }
+
+            match decode_ref(&rlp.at(1)?)? {
+                None => return Ok(expected_value.is_none()),
+                Some(next_ref) => pending_ref = next_ref,
+            }
+        } else {
+            return Err(anyhow!("unexpected MPT node with {} items", item_count));
+        }
+    }
+}
+
+/// RLP-encodes an account's state as the 4-item list `[nonce, balance, storageRoot, codeHash]`.
+pub fn encode_account(
+    nonce: u64,
+    balance: &[u8],
+    storage_root: &[u8; 32],
+    code_hash: &[u8; 32],
+) -> Vec<u8> {
+    let mut stream = RlpStream::new_list(4);
+    stream.append(&nonce);
+    stream.append(&balance);
+    stream.append(&storage_root.as_slice());
+    stream.append(&code_hash.as_slice());
+    stream.out().to_vec()
+}
+
+/// Verifies an account proof against a block's state root.
+///
+/// When `nonce`, `balance`, `storage_root`, and `code_hash` are all the
+/// defaults of a never-touched account, this verifies an exclusion proof
+/// instead — `eth_getProof` returns exactly these defaults (rather than
+/// failing) for an address that has no leaf in the trie. Otherwise this
+/// RLP-encodes `[nonce, balance, storageRoot, codeHash]` and walks
+/// `account_proof` from `state_root` along the nibbles of
+/// `keccak256(address)`, checking it terminates in a leaf whose value is that
+/// encoding.
+pub fn verify_account_proof(
+    state_root: [u8; 32],
+    address: &[u8],
+    nonce: u64,
+    balance: &[u8],
+    storage_root: [u8; 32],
+    code_hash: [u8; 32],
+    account_proof: &[Vec<u8>],
+) -> Result<bool> {
+    let path = to_nibbles(&keccak256(address));
+    let is_untouched_account = nonce == 0
+        && balance.iter().all(|b| *b == 0)
+        && storage_root == EMPTY_TRIE_ROOT
+        && code_hash == EMPTY_CODE_HASH;
+
+    if is_untouched_account {
+        return verify_branch(state_root, &path, account_proof, None);
+    }
+
+    let leaf_value = encode_account(nonce, balance, &storage_root, &code_hash);
+    verify_branch(state_root, &path, account_proof, Some(&leaf_value))
+}
+
+/// Verifies a storage proof against an account's storage root.
+///
+/// When `slot_value` is zero, this verifies an exclusion proof — `slot_value`
+/// is `0x0` for a slot that was never set, and `eth_getProof` returns a proof
+/// that the slot is absent from the storage trie rather than a leaf. For a
+/// non-zero value, the leaf value is the RLP-encoded slot value, per
+/// `eth_getProof` semantics.
+pub fn verify_storage_proof(
+    storage_root: [u8; 32],
+    storage_key: &[u8],
+    slot_value: &[u8],
+    storage_proof: &[Vec<u8>],
+) -> Result<bool> {
+    let path = to_nibbles(&keccak256(storage_key));
+
+    if slot_value.iter().all(|b| *b == 0) {
+        return verify_branch(storage_root, &path, storage_proof, None);
+    }
+
+    let mut stream = RlpStream::new();
+    stream.append(&slot_value);
+    let leaf_value = stream.out().to_vec();
+
+    verify_branch(storage_root, &path, storage_proof, Some(&leaf_value))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Hex-prefix encodes `path` per the MPT spec (the inverse of
+    /// `to_nibbles` + `strip_hex_prefix`), to build leaf/extension node
+    /// fixtures by hand.
+    fn hex_prefix(path: &[u8], terminator: bool) -> Vec<u8> {
+        let odd = path.len() % 2 == 1;
+        let flag = (if terminator { 2 } else { 0 }) + (odd as u8);
+        let mut nibbles = vec![flag];
+        if !odd {
+            nibbles.push(0);
+        }
+        nibbles.extend_from_slice(path);
+
+        nibbles
+            .chunks(2)
+            .map(|pair| (pair[0] << 4) | pair[1])
+            .collect()
+    }
+
+    /// A trie with a single key-value pair: the root is itself a leaf node.
+    fn single_leaf_trie(path: &[u8], value: &[u8]) -> ([u8; 32], Vec<Vec<u8>>) {
+        let mut stream = RlpStream::new_list(2);
+        stream.append(&hex_prefix(path, true));
+        stream.append(&value.to_vec());
+        let leaf = stream.out().to_vec();
+
+        (keccak256(&leaf), vec![leaf])
+    }
+
+    #[test]
+    fn verify_branch_accepts_matching_leaf_root() {
+        let path = vec![1, 2, 3, 4];
+        let value = b"value1".to_vec();
+        let (root, proof) = single_leaf_trie(&path, &value);
+
+        assert!(verify_branch(root, &path, &proof, Some(&value)).unwrap());
+    }
+
+    #[test]
+    fn verify_branch_rejects_wrong_value() {
+        let path = vec![1, 2, 3, 4];
+        let value = b"value1".to_vec();
+        let (root, proof) = single_leaf_trie(&path, &value);
+
+        assert!(!verify_branch(root, &path, &proof, Some(b"wrong")).unwrap());
+    }
+
+    #[test]
+    fn verify_branch_accepts_exclusion_on_diverging_leaf_path() {
+        let path = vec![1, 2, 3, 4];
+        let value = b"value1".to_vec();
+        let (root, proof) = single_leaf_trie(&path, &value);
+
+        let other_path = vec![1, 2, 9, 9];
+        assert!(verify_branch(root, &other_path, &proof, None).unwrap());
+        assert!(!verify_branch(root, &other_path, &proof, Some(&value)).unwrap());
+    }
+
+    /// A branch node at the root with one inline (non-hash-referenced) leaf
+    /// child at nibble `3`, and every other slot empty.
+    fn branch_with_inline_leaf() -> ([u8; 32], Vec<Vec<u8>>) {
+        let mut leaf_stream = RlpStream::new_list(2);
+        leaf_stream.append(&hex_prefix(&[7], true));
+        leaf_stream.append(&b"v".to_vec());
+        let leaf = leaf_stream.out().to_vec();
+        assert!(leaf.len() < 32, "fixture leaf must be inline-eligible");
+
+        let mut branch_stream = RlpStream::new_list(17);
+        for nibble in 0..16 {
+            if nibble == 3 {
+                branch_stream.append_raw(&leaf, 1);
+            } else {
+                branch_stream.append_empty_data();
+            }
+        }
+        branch_stream.append_empty_data();
+        let branch = branch_stream.out().to_vec();
+
+        (keccak256(&branch), vec![branch])
+    }
+
+    #[test]
+    fn verify_branch_recurses_into_inline_child_without_consuming_a_proof_node() {
+        let (root, proof) = branch_with_inline_leaf();
+
+        assert!(verify_branch(root, &[3, 7], &proof, Some(b"v")).unwrap());
+    }
+
+    #[test]
+    fn verify_branch_accepts_exclusion_on_empty_branch_slot() {
+        let (root, proof) = branch_with_inline_leaf();
+
+        assert!(verify_branch(root, &[5, 0], &proof, None).unwrap());
+        assert!(!verify_branch(root, &[5, 0], &proof, Some(b"v")).unwrap());
+    }
+
+    #[test]
+    fn verify_account_proof_accepts_matching_account() {
+        let address = b"0xaaaaaaaaaaaaaaaaaaaa".to_vec();
+        let path = to_nibbles(&keccak256(&address));
+        let storage_root = keccak256(b"some storage root");
+        let code_hash = keccak256(b"some code");
+        let leaf_value = encode_account(7, &[1, 0, 0], &storage_root, &code_hash);
+        let (root, proof) = single_leaf_trie(&path, &leaf_value);
+
+        assert!(
+            verify_account_proof(
+                root,
+                &address,
+                7,
+                &[1, 0, 0],
+                storage_root,
+                code_hash,
+                &proof
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn verify_account_proof_accepts_exclusion_for_untouched_account() {
+        let other_address = b"0xbbbbbbbbbbbbbbbbbbbb".to_vec();
+        let other_path = to_nibbles(&keccak256(&other_address));
+        let (root, proof) = single_leaf_trie(&other_path, b"unrelated leaf value");
+
+        let untouched_address = b"0xcccccccccccccccccccc".to_vec();
+        assert!(
+            verify_account_proof(
+                root,
+                &untouched_address,
+                0,
+                &[],
+                EMPTY_TRIE_ROOT,
+                EMPTY_CODE_HASH,
+                &proof
+            )
+            .unwrap()
+        );
+    }
+
+    #[test]
+    fn verify_storage_proof_accepts_exclusion_for_unset_slot() {
+        let other_key = b"some other storage key".to_vec();
+        let other_path = to_nibbles(&keccak256(&other_key));
+        let (root, proof) = single_leaf_trie(&other_path, b"unrelated leaf value");
+
+        let unset_key = b"an unset storage key".to_vec();
+        assert!(verify_storage_proof(root, &unset_key, &[], &proof).unwrap());
+    }
+}
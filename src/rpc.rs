@@ -0,0 +1,121 @@
+use anyhow::{Context, Result, anyhow};
+use serde::Deserialize;
+use serde_json::json;
+
+/// The symbolic block tags accepted alongside a concrete number or hash.
+const SYMBOLIC_TAGS: &[&str] = &["latest", "safe", "finalized", "earliest", "pending"];
+
+/// A block reference accepted in a request: a concrete number, one of the
+/// symbolic tags (`"latest"`, `"safe"`, `"finalized"`, `"earliest"`,
+/// `"pending"`), or a 32-byte block hash as a `0x`-prefixed hex string.
+///
+/// This lets a caller anchor a proof to a specific finalized block (by
+/// resolving `"finalized"` once and reusing the returned block hash) and
+/// detect a later reorg by noticing the hash for that number has changed.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum BlockSelector {
+    Number(u64),
+    Tag(String),
+}
+
+impl BlockSelector {
+    /// Resolves this selector to the concrete block it refers to.
+    pub async fn resolve(&self, rpc_url: &str) -> Result<EthBlock> {
+        match self {
+            BlockSelector::Number(height) => get_block_by_number(rpc_url, *height).await,
+            BlockSelector::Tag(tag) if SYMBOLIC_TAGS.contains(&tag.as_str()) => {
+                get_block_by_tag(rpc_url, tag).await
+            }
+            BlockSelector::Tag(hash) if hash.starts_with("0x") && hash.len() == 66 => {
+                get_block_by_hash(rpc_url, hash).await
+            }
+            BlockSelector::Tag(other) => Err(anyhow!(
+                "invalid block selector: {} (expected a number, a tag in {:?}, or a 32-byte block hash)",
+                other,
+                SYMBOLIC_TAGS
+            )),
+        }
+    }
+}
+
+/// Minimal fields pulled from an `eth_getBlockByNumber` / `eth_getBlockByHash` response.
+///
+/// Only the data this service needs to anchor and verify a proof is kept; the
+/// rest of the block body is discarded.
+#[derive(Debug, Clone, Deserialize)]
+pub struct EthBlock {
+    pub number: String,
+    pub hash: String,
+    #[serde(rename = "stateRoot")]
+    pub state_root: String,
+}
+
+#[derive(Deserialize)]
+struct JsonRpcResponse<T> {
+    result: Option<T>,
+    error: Option<JsonRpcError>,
+}
+
+#[derive(Deserialize)]
+struct JsonRpcError {
+    message: String,
+}
+
+/// Performs a single JSON-RPC call against an Ethereum node and decodes the `result`.
+async fn call<T: for<'de> Deserialize<'de>>(
+    rpc_url: &str,
+    method: &str,
+    params: serde_json::Value,
+) -> Result<T> {
+    let body = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": method,
+        "params": params,
+    });
+
+    let response: JsonRpcResponse<T> = reqwest::Client::new()
+        .post(rpc_url)
+        .json(&body)
+        .send()
+        .await
+        .with_context(|| format!("failed to reach RPC endpoint for {}", method))?
+        .json()
+        .await
+        .with_context(|| format!("failed to decode RPC response for {}", method))?;
+
+    if let Some(error) = response.error {
+        return Err(anyhow!("RPC error from {}: {}", method, error.message));
+    }
+
+    response
+        .result
+        .ok_or_else(|| anyhow!("RPC response for {} had no result", method))
+}
+
+/// Fetches a block by its number, without transaction bodies.
+pub async fn get_block_by_number(rpc_url: &str, height: u64) -> Result<EthBlock> {
+    call(
+        rpc_url,
+        "eth_getBlockByNumber",
+        json!([format!("0x{:x}", height), false]),
+    )
+    .await
+}
+
+/// Fetches a block by a symbolic tag (`"latest"`, `"safe"`, `"finalized"`, ...),
+/// without transaction bodies.
+pub async fn get_block_by_tag(rpc_url: &str, tag: &str) -> Result<EthBlock> {
+    call(rpc_url, "eth_getBlockByNumber", json!([tag, false])).await
+}
+
+/// Fetches a block by its hash, without transaction bodies.
+pub async fn get_block_by_hash(rpc_url: &str, block_hash: &str) -> Result<EthBlock> {
+    call(rpc_url, "eth_getBlockByHash", json!([block_hash, false])).await
+}
+
+/// Fetches the chain ID the RPC endpoint is serving, as a `0x`-prefixed hex string.
+pub async fn get_chain_id(rpc_url: &str) -> Result<String> {
+    call(rpc_url, "eth_chainId", json!([])).await
+}
@@ -4,92 +4,358 @@ use ethereum_merkle_proofs::{
 };
 use valence_coprocessor::StateProof;
 
-use anyhow::Result;
+use crate::domains;
+use crate::error::ProofError;
+use crate::merkle::{decode_hex, decode_hex32, verify_account_proof, verify_storage_proof};
+use crate::rpc::{BlockSelector, EthBlock, get_chain_id};
+use serde_json::json;
+
 /// Retrieves an Ethereum state proof for a given address and block height.
 ///
 /// This function generates either an account proof or a storage proof depending on whether
 /// a storage key is provided. The proof can be used to verify the state of an Ethereum
 /// account or a specific storage slot at a given block height.
 ///
+/// Before returning, the fetched block's `stateRoot` is retrieved via
+/// `eth_getBlockByNumber` (or `eth_getBlockByHash` when `height` is a block
+/// hash) and the proof is verified locally against it: the
+/// account leaf is checked against `[nonce, balance, storageRoot, codeHash]`,
+/// and storage proofs are additionally checked against the account's
+/// `storageRoot`. A proof that fails this check is never returned to the
+/// caller.
+///
 /// # Arguments
 ///
 /// * `address` - The Ethereum address to get the proof for (hex string, 0x-prefixed)
 /// * `ethereum_url` - The RPC URL for the Ethereum node (e.g., Infura, Alchemy)
-/// * `height` - The block height/number to get the proof for
+/// * `domain` - The chain this proof is sourced from, e.g. `"ethereum"`, `"base"`,
+///   `"arbitrum"`. Must be registered in [`domains`] and must match `ethereum_url`'s
+///   `eth_chainId`.
+/// * `height` - The block to get the proof for: a concrete number, a symbolic tag
+///   (`"latest"`, `"safe"`, `"finalized"`, `"earliest"`, `"pending"`), or a
+///   32-byte block hash
 /// * `key` - Optional storage slot key for storage proofs (hex string, 0x-prefixed)
 ///
 /// # Returns
 ///
 /// Returns a `StateProof` containing:
-/// * `domain` - Always set to "ethereum"
-/// * `root` - The Merkle root (currently set to zero, TODO: implement)
-/// * `payload` - Additional data (currently empty)
+/// * `domain` - The requested domain, e.g. "ethereum"
+/// * `root` - The verified `stateRoot` of the block at `height`
+/// * `payload` - The resolved `block_number` and `block_hash` the proof is anchored to,
+///   JSON-encoded, so a caller using a symbolic tag or hash can tell exactly which
+///   block was used and detect a later reorg
 /// * `proof` - The serialized proof bytes containing either:
 ///   * An account proof - when no storage key is provided
 ///   * A storage proof - when a storage key is provided
 ///
 /// # Errors
 ///
-/// Returns an error if:
+/// Returns `ProofError::Rpc` if:
 /// * The Ethereum RPC request fails
 /// * The proof generation fails
 /// * The proof serialization fails
 ///
+/// Returns `ProofError::DomainMismatch` if `domain` is not registered, or if
+/// `ethereum_url`'s `eth_chainId` doesn't match the domain's chain ID.
+///
+/// Returns `ProofError::VerificationFailed` if the fetched proof does not
+/// verify against the block's `stateRoot`, so the service never serves an
+/// unverifiable proof.
+///
 /// # Example
 ///
 /// ```rust
 /// let proof = get_state_proof(
 ///     "0x1234...",
 ///     "https://eth-mainnet.alchemyapi.io/v2/your-api-key",
-///     12345678,
+///     "ethereum",
+///     &BlockSelector::Tag("finalized".to_string()),
 ///     None
 /// ).await?;
 /// ```
 pub async fn get_state_proof(
     address: &str,
     ethereum_url: &str,
-    height: u64,
+    domain: &str,
+    height: &BlockSelector,
     key: Option<&str>,
-) -> Result<Vec<u8>> {
+) -> Result<Vec<u8>, ProofError> {
+    let state_proof = build_state_proof(address, ethereum_url, domain, height, key).await?;
+    Ok(serde_json::to_vec(&state_proof)?)
+}
+
+/// Fetches a state proof from each of `ethereum_urls` concurrently and only
+/// succeeds once at least `min_agreement` of them produce a byte-identical
+/// state root and proof.
+///
+/// This defends against a single compromised or out-of-sync RPC provider: an
+/// endpoint serving a stale or forged view of the chain will disagree with
+/// the rest, and on disagreement this returns an error naming the endpoints
+/// whose proofs diverged from the majority instead of trusting any one of
+/// them.
+///
+/// # Errors
+///
+/// Returns `ProofError::Rpc` if every endpoint fails to produce a proof, and
+/// `ProofError::VerificationFailed` if no group of at least `min_agreement`
+/// endpoints agrees.
+pub async fn get_state_proof_quorum(
+    address: &str,
+    ethereum_urls: &[String],
+    domain: &str,
+    min_agreement: usize,
+    height: &BlockSelector,
+    key: Option<&str>,
+) -> Result<Vec<u8>, ProofError> {
+    let results = futures::future::join_all(ethereum_urls.iter().map(|url| async move {
+        (
+            url.clone(),
+            build_state_proof(address, url, domain, height, key).await,
+        )
+    }))
+    .await;
+
+    let mut groups: Vec<(StateProof, Vec<String>)> = Vec::new();
+    let mut failures: Vec<(String, ProofError)> = Vec::new();
+
+    for (url, result) in results {
+        match result {
+            Ok(proof) => {
+                if let Some((_, urls)) = groups
+                    .iter_mut()
+                    .find(|(existing, _)| existing.root == proof.root && existing.proof == proof.proof)
+                {
+                    urls.push(url);
+                } else {
+                    groups.push((proof, vec![url]));
+                }
+            }
+            Err(e) => failures.push((url, e)),
+        }
+    }
+
+    let mut by_agreement: Vec<&(StateProof, Vec<String>)> = groups.iter().collect();
+    by_agreement.sort_by_key(|(_, urls)| std::cmp::Reverse(urls.len()));
+
+    if let Some((proof, urls)) = by_agreement.first() {
+        let is_unique_max = by_agreement
+            .get(1)
+            .map(|(_, runner_up)| runner_up.len() < urls.len())
+            .unwrap_or(true);
+        if urls.len() >= min_agreement && is_unique_max {
+            return Ok(serde_json::to_vec(proof)?);
+        }
+    }
+
+    let mut diverging: Vec<String> = groups
+        .iter()
+        .map(|(_, urls)| format!("[{}]", urls.join(", ")))
+        .collect();
+    diverging.extend(
+        failures
+            .iter()
+            .map(|(url, e)| format!("{} (error: {})", url, e)),
+    );
+
+    if groups.is_empty() && !failures.is_empty() {
+        return Err(ProofError::Rpc(anyhow::anyhow!(
+            "every endpoint failed for address {} at height {:?}: {}",
+            address,
+            height,
+            diverging.join(" | ")
+        )));
+    }
+
+    Err(ProofError::VerificationFailed(format!(
+        "no quorum of {} reached for address {} at height {:?}; divergent groups: {}",
+        min_agreement,
+        address,
+        height,
+        diverging.join(" | ")
+    )))
+}
+
+/// Checks that `ethereum_url`'s `eth_chainId` matches the chain ID registered
+/// for `domain`, so a proof is never built against the wrong chain's RPC
+/// endpoint.
+///
+/// Split out of [`build_state_proof`] so callers that already know which
+/// block they're anchoring to (e.g. the `/ws` handler, reusing the block from
+/// a `newHeads` notification) can validate the domain once per connection
+/// instead of on every proof built.
+pub(crate) async fn validate_domain(ethereum_url: &str, domain: &str) -> Result<(), ProofError> {
+    let chain = domains::resolve(domain).map_err(|e| ProofError::DomainMismatch(e.to_string()))?;
+
+    let reported_chain_id = get_chain_id(ethereum_url)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to fetch chain ID from {}: {}", ethereum_url, e))?;
+    let reported_chain_id = u64::from_str_radix(
+        reported_chain_id.trim_start_matches("0x"),
+        16,
+    )
+    .map_err(|e| anyhow::anyhow!("Malformed eth_chainId response: {}", e))?;
+
+    if reported_chain_id != chain.chain_id {
+        return Err(ProofError::DomainMismatch(format!(
+            "ethereum_url reports chain ID {} but domain {:?} expects chain ID {}",
+            reported_chain_id, domain, chain.chain_id
+        )));
+    }
+
+    Ok(())
+}
+
+/// Builds a verified `StateProof` for a single RPC endpoint.
+///
+/// This is the shared core used by both [`get_state_proof`] and
+/// [`get_state_proof_quorum`].
+async fn build_state_proof(
+    address: &str,
+    ethereum_url: &str,
+    domain: &str,
+    height: &BlockSelector,
+    key: Option<&str>,
+) -> Result<StateProof, ProofError> {
+    validate_domain(ethereum_url, domain).await?;
+
+    let block = height
+        .resolve(ethereum_url)
+        .await
+        .map_err(|e| anyhow::anyhow!("Failed to resolve block: {}", e))?;
+
+    build_state_proof_for_block(address, ethereum_url, domain, &block, key).await
+}
+
+/// Builds a verified `StateProof` for a single RPC endpoint, against an
+/// already-resolved block.
+///
+/// Unlike [`build_state_proof`], this performs no domain/chain-ID validation
+/// and makes no call to resolve `height` into a block — both are assumed to
+/// have already been done by the caller. This lets a caller that processes a
+/// stream of blocks (e.g. the `/ws` handler, on each `newHeads` notification)
+/// reuse a block it already has instead of re-fetching it per proof.
+pub(crate) async fn build_state_proof_for_block(
+    address: &str,
+    ethereum_url: &str,
+    domain: &str,
+    block: &EthBlock,
+    key: Option<&str>,
+) -> Result<StateProof, ProofError> {
     let merkle_prover = EvmMerkleRpcClient {
         rpc_url: ethereum_url.to_string(),
     };
 
+    let state_root = decode_hex32(&block.state_root)?;
+    let resolved_height = u64::from_str_radix(block.number.trim_start_matches("0x"), 16)
+        .map_err(|e| anyhow::anyhow!("Malformed block number {}: {}", block.number, e))?;
+    let address_bytes = decode_hex(address)?;
+
     let state_proof = match key {
         Some(key) => {
             let combined_proof = merkle_prover
-                .get_account_and_storage_proof(key, address, height)
+                .get_account_and_storage_proof(key, address, resolved_height)
                 .await
                 .map_err(|e| anyhow::anyhow!("Failed to get storage proof: {}", e))?;
 
+            let storage_root = decode_hex32(&combined_proof.account_proof.storage_hash)?;
+            let verified = verify_account_proof(
+                state_root,
+                &address_bytes,
+                combined_proof.account_proof.nonce,
+                &decode_hex(&combined_proof.account_proof.balance)?,
+                storage_root,
+                decode_hex32(&combined_proof.account_proof.code_hash)?,
+                &to_node_bytes(&combined_proof.account_proof.account_proof)?,
+            )
+            .map_err(|e| ProofError::VerificationFailed(format!("malformed account proof: {}", e)))?;
+
+            if !verified {
+                return Err(ProofError::VerificationFailed(format!(
+                    "account proof for {} does not match state root at height {}",
+                    address, resolved_height
+                )));
+            }
+
+            let storage_key_bytes = decode_hex(key)?;
+            let storage_verified = verify_storage_proof(
+                storage_root,
+                &storage_key_bytes,
+                &decode_hex(&combined_proof.value)?,
+                &to_node_bytes(&combined_proof.storage_proof)?,
+            )
+            .map_err(|e| ProofError::VerificationFailed(format!("malformed storage proof: {}", e)))?;
+
+            if !storage_verified {
+                return Err(ProofError::VerificationFailed(format!(
+                    "storage proof for {} key {} does not match account storage root at height {}",
+                    address, key, resolved_height
+                )));
+            }
+
             let simple_proof = EthereumSimpleProof::from_combined_proof(combined_proof);
             let proof = EthereumProofType::Simple(simple_proof);
             let proof_bytes = serde_json::to_vec(&proof)?;
 
             StateProof {
-                domain: "ethereum".to_string(),
-                root: [0u8; 32],
-                payload: Vec::new(),
+                domain: domain.to_string(),
+                root: state_root,
+                payload: block_anchor_payload(resolved_height, &block.hash)?,
                 proof: proof_bytes,
             }
         }
         None => {
             let account_proof = merkle_prover
-                .get_account_proof(address, height)
+                .get_account_proof(address, resolved_height)
                 .await
                 .map_err(|e| anyhow::anyhow!("Failed to get account proof: {}", e))?;
 
+            let verified = verify_account_proof(
+                state_root,
+                &address_bytes,
+                account_proof.nonce,
+                &decode_hex(&account_proof.balance)?,
+                decode_hex32(&account_proof.storage_hash)?,
+                decode_hex32(&account_proof.code_hash)?,
+                &to_node_bytes(&account_proof.account_proof)?,
+            )
+            .map_err(|e| ProofError::VerificationFailed(format!("malformed account proof: {}", e)))?;
+
+            if !verified {
+                return Err(ProofError::VerificationFailed(format!(
+                    "account proof for {} does not match state root at height {}",
+                    address, resolved_height
+                )));
+            }
+
             let proof = EthereumProofType::Account(account_proof);
             let proof_bytes = serde_json::to_vec(&proof)?;
 
             StateProof {
-                domain: "ethereum".to_string(),
-                root: [0u8; 32],
-                payload: Vec::new(),
+                domain: domain.to_string(),
+                root: state_root,
+                payload: block_anchor_payload(resolved_height, &block.hash)?,
                 proof: proof_bytes,
             }
         }
     };
 
-    Ok(serde_json::to_vec(&state_proof)?)
+    Ok(state_proof)
+}
+
+/// Decodes a list of `0x`-prefixed hex-encoded trie nodes into raw bytes.
+fn to_node_bytes(nodes: &[String]) -> anyhow::Result<Vec<Vec<u8>>> {
+    nodes.iter().map(|node| decode_hex(node)).collect()
+}
+
+/// Encodes the resolved block number and hash a proof is anchored to.
+///
+/// Lets a caller that requested a symbolic tag (`"latest"`, `"finalized"`, ...)
+/// or a bare block hash find out which concrete block the proof actually
+/// commits to, and detect a reorg later by noticing the hash for that number
+/// has changed.
+fn block_anchor_payload(block_number: u64, block_hash: &str) -> anyhow::Result<Vec<u8>> {
+    Ok(serde_json::to_vec(&json!({
+        "block_number": block_number,
+        "block_hash": block_hash,
+    }))?)
 }
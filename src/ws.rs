@@ -0,0 +1,220 @@
+use axum::extract::State;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::response::IntoResponse;
+use futures::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::json;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use tokio::sync::Mutex;
+use tokio_tungstenite::tungstenite::Message as UpstreamMessage;
+
+use crate::rpc::EthBlock;
+use crate::util::{build_state_proof_for_block, validate_domain};
+
+/// A single account (and optionally storage slot) a client wants fresh proofs for.
+#[derive(Debug, Clone, Deserialize)]
+pub struct WatchTarget {
+    pub address: String,
+    #[serde(default)]
+    pub key: Option<String>,
+}
+
+/// The first message a client sends after connecting: the node to watch and
+/// the set of accounts/slots to produce proofs for on every new head.
+#[derive(Debug, Deserialize)]
+struct Subscribe {
+    ethereum_url: String,
+    #[serde(default = "default_domain")]
+    domain: String,
+    targets: Vec<WatchTarget>,
+}
+
+fn default_domain() -> String {
+    "ethereum".to_string()
+}
+
+type ConnectionId = u64;
+
+/// Tracks the watch list for every open `/ws` connection.
+///
+/// Shared as Axum state so each connection's handler can register and look up
+/// its own watch list without the connections needing to know about each
+/// other. Entries are removed once their connection closes.
+#[derive(Default, Clone)]
+pub struct SubscriptionManager {
+    next_id: Arc<AtomicU64>,
+    watches: Arc<Mutex<HashMap<ConnectionId, Vec<WatchTarget>>>>,
+}
+
+impl SubscriptionManager {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    async fn open(&self) -> ConnectionId {
+        let id = self.next_id.fetch_add(1, Ordering::SeqCst);
+        self.watches.lock().await.insert(id, Vec::new());
+        id
+    }
+
+    async fn set_targets(&self, id: ConnectionId, targets: Vec<WatchTarget>) {
+        if let Some(entry) = self.watches.lock().await.get_mut(&id) {
+            *entry = targets;
+        }
+    }
+
+    async fn targets(&self, id: ConnectionId) -> Vec<WatchTarget> {
+        self.watches
+            .lock()
+            .await
+            .get(&id)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    async fn close(&self, id: ConnectionId) {
+        self.watches.lock().await.remove(&id);
+    }
+}
+
+/// Rewrites an `http(s)://` RPC URL into the `ws(s)://` URL most nodes serve
+/// their subscription API on.
+fn derive_ws_url(http_url: &str) -> String {
+    if let Some(rest) = http_url.strip_prefix("https://") {
+        format!("wss://{}", rest)
+    } else if let Some(rest) = http_url.strip_prefix("http://") {
+        format!("ws://{}", rest)
+    } else {
+        http_url.to_string()
+    }
+}
+
+/// Upgrades the connection to a WebSocket and starts streaming proofs for the
+/// watch list the client registers.
+pub async fn watch_handler(
+    ws: WebSocketUpgrade,
+    State(manager): State<SubscriptionManager>,
+) -> impl IntoResponse {
+    ws.on_upgrade(move |socket| handle_socket(socket, manager))
+}
+
+/// Per-connection loop: waits for the client's watch-list registration, opens
+/// an upstream `eth_subscribe("newHeads")` connection to the node, and pushes
+/// a fresh proof for every watched target on each new head until the client
+/// disconnects.
+async fn handle_socket(mut socket: WebSocket, manager: SubscriptionManager) {
+    let id = manager.open().await;
+
+    let subscribe = match socket.recv().await {
+        Some(Ok(Message::Text(text))) => match serde_json::from_str::<Subscribe>(&text) {
+            Ok(subscribe) => subscribe,
+            Err(e) => {
+                let _ = socket
+                    .send(Message::Text(
+                        json!({"error": format!("invalid subscribe message: {}", e)}).to_string(),
+                    ))
+                    .await;
+                manager.close(id).await;
+                return;
+            }
+        },
+        _ => {
+            manager.close(id).await;
+            return;
+        }
+    };
+
+    manager.set_targets(id, subscribe.targets).await;
+
+    if let Err(e) = validate_domain(&subscribe.ethereum_url, &subscribe.domain).await {
+        let _ = socket
+            .send(Message::Text(
+                json!({"error": format!("domain validation failed: {}", e)}).to_string(),
+            ))
+            .await;
+        manager.close(id).await;
+        return;
+    }
+
+    let upstream = match tokio_tungstenite::connect_async(derive_ws_url(&subscribe.ethereum_url)).await
+    {
+        Ok((stream, _)) => stream,
+        Err(e) => {
+            let _ = socket
+                .send(Message::Text(
+                    json!({"error": format!("failed to connect to upstream node: {}", e)})
+                        .to_string(),
+                ))
+                .await;
+            manager.close(id).await;
+            return;
+        }
+    };
+    let (mut upstream_write, mut upstream_read) = upstream.split();
+
+    let newheads_request = json!({
+        "jsonrpc": "2.0",
+        "id": 1,
+        "method": "eth_subscribe",
+        "params": ["newHeads"],
+    });
+    if upstream_write
+        .send(UpstreamMessage::Text(newheads_request.to_string()))
+        .await
+        .is_err()
+    {
+        manager.close(id).await;
+        return;
+    }
+
+    while let Some(Ok(message)) = upstream_read.next().await {
+        let UpstreamMessage::Text(text) = message else {
+            continue;
+        };
+        let Ok(notification) = serde_json::from_str::<serde_json::Value>(&text) else {
+            continue;
+        };
+        let Some(header) = notification.pointer("/params/result") else {
+            continue;
+        };
+        let Ok(block) = serde_json::from_value::<EthBlock>(header.clone()) else {
+            continue;
+        };
+        let Ok(height) = u64::from_str_radix(block.number.trim_start_matches("0x"), 16) else {
+            continue;
+        };
+
+        let targets = manager.targets(id).await;
+        let proofs = futures::future::join_all(targets.iter().map(|target| {
+            let block = &block;
+            async move {
+                match build_state_proof_for_block(
+                    &target.address,
+                    &subscribe.ethereum_url,
+                    &subscribe.domain,
+                    block,
+                    target.key.as_deref(),
+                )
+                .await
+                {
+                    Ok(proof) => {
+                        json!({"address": target.address, "key": target.key, "proof": proof})
+                    }
+                    Err(e) => {
+                        json!({"address": target.address, "key": target.key, "error": e.to_string()})
+                    }
+                }
+            }
+        }))
+        .await;
+
+        let push = json!({"height": height, "proofs": proofs});
+        if socket.send(Message::Text(push.to_string())).await.is_err() {
+            break;
+        }
+    }
+
+    manager.close(id).await;
+}